@@ -1,10 +1,68 @@
-use lightning::ln::PaymentHash;
+use bech32::{Bech32Writer, FromBase32, ToBase32, Variant};
+use bitcoin::psbt::raw::ProprietaryKey;
+use bitcoin::psbt::PartiallySignedTransaction;
+use bitcoin::{OutPoint, Script, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness};
+use bitcoin::hashes::{sha256, Hash};
+use lightning::ln::{PaymentHash, PaymentPreimage};
 use rgbstd::contract::ContractId;
+use serde::{Deserialize, Serialize};
 use std::convert::TryInto;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use crate::utils::hex_str_to_vec;
 
+/// Dust limit (in sats) at or below which a change output is dropped rather than
+/// added to a PSBT, matching the "full transfer" coin-selection flow used
+/// elsewhere for RGB transfers.
+pub const DUST_LIMIT_SAT: u64 = 546;
+
+/// Proprietary key prefix used to stash a serialized RGB state transition
+/// bundle on the PSBT output it commits to.
+const PSBT_RGB_PREFIX: &[u8] = b"RGB";
+
+/// Human-readable prefix for the bech32-encoded swap string format.
+const SWAP_HRP: &str = "swap";
+
+/// Marker byte used in the bech32 data part to denote the BTC leg of a swap
+/// (i.e. an asset field that is `None`).
+const ASSET_MARKER_BTC: u8 = 0x00;
+/// Marker byte used in the bech32 data part to denote an RGB contract ID.
+const ASSET_MARKER_CONTRACT: u8 = 0x01;
+
+/// Presence marker bytes for optional integer fields (e.g. a `SwapOffer`'s
+/// `min`/`max` quantity bounds).
+const OPT_MARKER_NONE: u8 = 0x00;
+const OPT_MARKER_SOME: u8 = 0x01;
+
+/// Errors returned when encoding or decoding a [`SwapString`] to/from its
+/// bech32 representation.
+#[derive(Debug)]
+pub enum SwapBech32Error {
+    /// The string could not be decoded as valid bech32.
+    Bech32(bech32::Error),
+    /// The human-readable part was not the expected `swap` prefix.
+    WrongHrp,
+    /// The bech32 variant was not the one we encode with.
+    WrongVariant,
+    /// The data part ended before all the expected fields were read.
+    UnexpectedEnd,
+    /// A length-prefixed integer field was longer than 8 bytes and could not
+    /// fit in a `u64` without overflowing.
+    Overflow,
+    /// An asset marker byte was neither the BTC marker nor the contract ID
+    /// marker, or an optional integer's presence marker byte was neither
+    /// the none nor the some marker.
+    InvalidAssetMarker,
+    /// A contract ID could not be parsed from its encoded bytes.
+    InvalidContractId,
+    /// The decoded fields don't form a valid [`SwapOffer`] (same-asset
+    /// legs, a zero price, or a `min_qty_from` above `max_qty_from`).
+    InvalidOffer,
+}
+
 #[derive(Debug, Clone)]
 pub struct Swap {
     pub(crate) qty_from: u64,
@@ -26,7 +84,7 @@ impl Swap {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SwapString {
     pub swap: Swap,
     pub expiry: u64,
@@ -105,3 +163,1927 @@ pub fn parse_opt_asset(asset: &str) -> Result<Option<ContractId>, baid58::Baid58
         ContractId::from_str(asset).map(Option::Some)
     }
 }
+
+impl SwapString {
+    /// Encode this swap string into its bech32 representation (HRP `swap`).
+    ///
+    /// The data part packs `qty_from`, `from_asset`, `qty_to`, `to_asset` and
+    /// `expiry` as length-prefixed big-endian integers / asset markers,
+    /// followed by the raw 32-byte payment hash, and is checksummed by
+    /// bech32 so a mistyped or truncated string is caught at decode time
+    /// instead of silently producing a wrong swap.
+    pub fn to_bech32(&self) -> String {
+        let mut data = Vec::new();
+        push_int(&mut data, self.swap.qty_from);
+        push_asset(&mut data, self.swap.from_asset);
+        push_int(&mut data, self.swap.qty_to);
+        push_asset(&mut data, self.swap.to_asset);
+        push_int(&mut data, self.expiry);
+        data.extend_from_slice(&self.payment_hash.0);
+
+        encode_bech32(SWAP_HRP, &data)
+    }
+
+    /// Decode a [`SwapString`] from its bech32 representation.
+    ///
+    /// This runs a tiny state machine over the input: consume the
+    /// human-readable prefix, locate the `1` separator, then hand the rest
+    /// to `bech32::decode` to verify the checksum before extracting fields.
+    pub fn from_bech32(s: &str) -> Result<Self, SwapBech32Error> {
+        let data = decode_bech32_data(s, SWAP_HRP)?;
+
+        let mut reader = ByteReader::new(&data);
+        let qty_from = reader.read_int()?;
+        let from_asset = reader.read_asset()?;
+        let qty_to = reader.read_int()?;
+        let to_asset = reader.read_asset()?;
+        let expiry = reader.read_int()?;
+        let payment_hash = reader.read_payment_hash()?;
+
+        let swap = Swap {
+            qty_from,
+            qty_to,
+            from_asset,
+            to_asset,
+        };
+
+        Ok(SwapString {
+            swap,
+            expiry,
+            payment_hash,
+        })
+    }
+}
+
+/// Encode `data` as the data part of a bech32 string with the given HRP,
+/// streaming 5-bit groups through a [`Bech32Writer`] rather than calling
+/// `bech32::encode`. BIP-173's 90-character total length cap only applies
+/// to that top-level helper (meant for human-typed strings like addresses);
+/// `Bech32Writer` is the same mechanism `lightning-invoice` uses to encode
+/// BOLT11 invoices, which routinely exceed 90 characters.
+fn encode_bech32(hrp: &str, data: &[u8]) -> String {
+    let mut out = String::new();
+    {
+        let mut writer =
+            Bech32Writer::new(hrp, Variant::Bech32, &mut out).expect("HRP is valid ASCII");
+        for u5 in data.to_base32() {
+            writer.write_u5(u5).expect("writing to a String never fails");
+        }
+    }
+    out
+}
+
+/// Run the HRP/separator state machine and verify the bech32 checksum,
+/// returning the decoded data part on success.
+///
+/// Consumes the human-readable prefix, locates the `1` separator, then hands
+/// the rest to `bech32::decode` to verify the checksum before any field is
+/// extracted.
+fn decode_bech32_data(s: &str, expected_hrp: &str) -> Result<Vec<u8>, SwapBech32Error> {
+    let sep_pos = s.rfind('1').ok_or(SwapBech32Error::WrongHrp)?;
+    if !s[..sep_pos].eq_ignore_ascii_case(expected_hrp) {
+        return Err(SwapBech32Error::WrongHrp);
+    }
+
+    let (hrp, data, variant) = bech32::decode(s).map_err(SwapBech32Error::Bech32)?;
+    if hrp != expected_hrp {
+        return Err(SwapBech32Error::WrongHrp);
+    }
+    if variant != Variant::Bech32 {
+        return Err(SwapBech32Error::WrongVariant);
+    }
+    Vec::<u8>::from_base32(&data).map_err(SwapBech32Error::Bech32)
+}
+
+/// Push a `u64` onto `data` as a one-byte length followed by its minimal
+/// big-endian representation (no leading zero bytes).
+fn push_int(data: &mut Vec<u8>, value: u64) {
+    let bytes = value.to_be_bytes();
+    let first_nonzero = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len() - 1);
+    let trimmed = &bytes[first_nonzero..];
+    data.push(trimmed.len() as u8);
+    data.extend_from_slice(trimmed);
+}
+
+/// Push an `Option<ContractId>` onto `data` as a marker byte, followed by the
+/// 32-byte contract ID bytes when present.
+fn push_asset(data: &mut Vec<u8>, asset: Option<ContractId>) {
+    match asset {
+        None => data.push(ASSET_MARKER_BTC),
+        Some(contract_id) => {
+            data.push(ASSET_MARKER_CONTRACT);
+            data.extend_from_slice(&contract_id.to_byte_array());
+        }
+    }
+}
+
+/// Push an `Option<u64>` onto `data` as a presence marker byte, followed by
+/// the length-prefixed integer when present.
+fn push_opt_int(data: &mut Vec<u8>, value: Option<u64>) {
+    match value {
+        None => data.push(OPT_MARKER_NONE),
+        Some(value) => {
+            data.push(OPT_MARKER_SOME);
+            push_int(data, value);
+        }
+    }
+}
+
+/// A small cursor over the decoded bech32 data part, used to pull out the
+/// length-prefixed integers, asset markers and payment hash in order.
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        ByteReader { data, pos: 0 }
+    }
+
+    fn read_byte(&mut self) -> Result<u8, SwapBech32Error> {
+        let byte = *self.data.get(self.pos).ok_or(SwapBech32Error::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], SwapBech32Error> {
+        let end = self.pos.checked_add(len).ok_or(SwapBech32Error::UnexpectedEnd)?;
+        let slice = self
+            .data
+            .get(self.pos..end)
+            .ok_or(SwapBech32Error::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    /// Read a length-prefixed big-endian integer, accumulating it one byte
+    /// at a time via checked multiply-then-add so a field longer than fits
+    /// in a `u64` returns [`SwapBech32Error::Overflow`] instead of wrapping.
+    fn read_int(&mut self) -> Result<u64, SwapBech32Error> {
+        let len = self.read_byte()? as usize;
+        let bytes = self.read_bytes(len)?;
+        let mut value: u64 = 0;
+        for byte in bytes {
+            value = value
+                .checked_mul(256)
+                .and_then(|v| v.checked_add(u64::from(*byte)))
+                .ok_or(SwapBech32Error::Overflow)?;
+        }
+        Ok(value)
+    }
+
+    /// Read an optional length-prefixed integer, as written by
+    /// [`push_opt_int`].
+    fn read_opt_int(&mut self) -> Result<Option<u64>, SwapBech32Error> {
+        match self.read_byte()? {
+            OPT_MARKER_NONE => Ok(None),
+            OPT_MARKER_SOME => self.read_int().map(Some),
+            _ => Err(SwapBech32Error::InvalidAssetMarker),
+        }
+    }
+
+    fn read_asset(&mut self) -> Result<Option<ContractId>, SwapBech32Error> {
+        match self.read_byte()? {
+            ASSET_MARKER_BTC => Ok(None),
+            ASSET_MARKER_CONTRACT => {
+                let bytes = self.read_bytes(32)?;
+                let contract_id = ContractId::from_byte_array(
+                    bytes.try_into().map_err(|_| SwapBech32Error::InvalidContractId)?,
+                );
+                Ok(Some(contract_id))
+            }
+            _ => Err(SwapBech32Error::InvalidAssetMarker),
+        }
+    }
+
+    fn read_payment_hash(&mut self) -> Result<PaymentHash, SwapBech32Error> {
+        let bytes = self.read_bytes(32)?;
+        let array: [u8; 32] = bytes.try_into().map_err(|_| SwapBech32Error::InvalidContractId)?;
+        Ok(PaymentHash(array))
+    }
+}
+
+#[cfg(test)]
+mod bech32_tests {
+    use super::*;
+
+    fn sample_swap_string(asset: Option<ContractId>) -> SwapString {
+        SwapString {
+            swap: Swap {
+                qty_from: 123_456,
+                qty_to: 42,
+                from_asset: asset,
+                to_asset: None,
+            },
+            expiry: 144,
+            payment_hash: PaymentHash([7u8; 32]),
+        }
+    }
+
+    #[test]
+    fn bech32_round_trips_btc_leg() {
+        let swap_string = sample_swap_string(None);
+        let encoded = swap_string.to_bech32();
+        let decoded = SwapString::from_bech32(&encoded).unwrap();
+        assert_eq!(decoded.swap.qty_from, swap_string.swap.qty_from);
+        assert_eq!(decoded.swap.qty_to, swap_string.swap.qty_to);
+        assert_eq!(decoded.expiry, swap_string.expiry);
+        assert_eq!(decoded.payment_hash, swap_string.payment_hash);
+    }
+
+    #[test]
+    fn bech32_round_trips_and_does_not_panic_with_a_real_contract_id() {
+        // A real asset leg pushes a 32-byte contract ID into the data part,
+        // which base32-expands well past bech32's 90-character address cap;
+        // `to_bech32` must not panic on this, ordinary, input.
+        let contract_id = ContractId::from_byte_array([9u8; 32]);
+        let swap_string = sample_swap_string(Some(contract_id));
+        let encoded = swap_string.to_bech32();
+        assert!(encoded.len() > 90);
+        let decoded = SwapString::from_bech32(&encoded).unwrap();
+        assert_eq!(decoded.swap.from_asset, Some(contract_id));
+    }
+
+    #[test]
+    fn bech32_decode_rejects_wrong_hrp() {
+        let other = bech32::encode("notswap", Vec::<u8>::new().to_base32(), Variant::Bech32).unwrap();
+        assert!(matches!(
+            SwapString::from_bech32(&other),
+            Err(SwapBech32Error::WrongHrp)
+        ));
+    }
+
+    #[test]
+    fn bech32_decode_detects_corruption() {
+        let mut encoded = sample_swap_string(None).to_bech32();
+        let last = encoded.pop().unwrap();
+        encoded.push(if last == 'q' { 'p' } else { 'q' });
+        assert!(matches!(
+            SwapString::from_bech32(&encoded),
+            Err(SwapBech32Error::Bech32(_))
+        ));
+    }
+
+    #[test]
+    fn read_int_overflows_on_an_over_long_field() {
+        // length byte says 9 bytes follow, which can never fit in a u64.
+        let mut data = vec![9u8];
+        data.extend_from_slice(&[0xFFu8; 9]);
+        let mut reader = ByteReader::new(&data);
+        assert!(matches!(reader.read_int(), Err(SwapBech32Error::Overflow)));
+    }
+
+    #[test]
+    fn read_int_round_trips_values() {
+        for value in [0u64, 1, 255, 256, u32::MAX as u64, u64::MAX] {
+            let mut data = Vec::new();
+            push_int(&mut data, value);
+            let mut reader = ByteReader::new(&data);
+            assert_eq!(reader.read_int().unwrap(), value);
+        }
+    }
+}
+
+/// Human-readable prefix for the bech32-encoded swap offer format.
+const SWAP_OFFER_HRP: &str = "swapoffer";
+
+/// A reusable, published swap offer, modeled on BOLT12 offers: it describes
+/// an asset pair and the price a maker is willing to trade at, without being
+/// pinned to a single counterparty or `PaymentHash` up front. A taker derives
+/// a concrete, single-use [`SwapString`] from it via [`SwapOffer::derive_swap`].
+#[derive(Debug, Clone)]
+pub struct SwapOffer {
+    pub from_asset: Option<ContractId>,
+    pub to_asset: Option<ContractId>,
+    /// Price expressed as `qty_to` per `qty_from`, i.e. a taker requesting
+    /// `qty_from` gets `qty_from * price_num / price_denom` of `to_asset`.
+    pub price_num: u64,
+    pub price_denom: u64,
+    /// Inclusive lower bound on the `qty_from` a taker may request.
+    pub min_qty_from: Option<u64>,
+    /// Inclusive upper bound on the `qty_from` a taker may request.
+    pub max_qty_from: Option<u64>,
+    /// Absolute unix timestamp from which on the offer itself can no longer
+    /// be taken (distinct from the relative HTLC `expiry` of a derived swap).
+    pub offer_expiry: u64,
+}
+
+/// Errors returned when constructing a [`SwapOffer`] or deriving a
+/// [`SwapString`] from one.
+#[derive(Debug)]
+pub enum SwapOfferError {
+    /// `from_asset` and `to_asset` are the same asset.
+    SameAsset,
+    /// `price_num` or `price_denom` is zero.
+    ZeroPrice,
+    /// The offer itself has already expired.
+    Expired,
+    /// The requested `qty_from` is below `min_qty_from`.
+    QtyTooLow,
+    /// The requested `qty_from` is above `max_qty_from`.
+    QtyTooHigh,
+    /// `min_qty_from` is greater than `max_qty_from`.
+    InvalidQtyRange,
+    /// Computing `qty_to` from the price ratio overflowed, or rounded down
+    /// to zero.
+    InvalidQtyTo,
+}
+
+impl SwapOffer {
+    pub fn new(
+        from_asset: Option<ContractId>,
+        to_asset: Option<ContractId>,
+        price_num: u64,
+        price_denom: u64,
+        min_qty_from: Option<u64>,
+        max_qty_from: Option<u64>,
+        offer_expiry: u64,
+    ) -> Result<Self, SwapOfferError> {
+        if from_asset == to_asset {
+            return Err(SwapOfferError::SameAsset);
+        }
+        if price_num == 0 || price_denom == 0 {
+            return Err(SwapOfferError::ZeroPrice);
+        }
+        if let (Some(min), Some(max)) = (min_qty_from, max_qty_from) {
+            if min > max {
+                return Err(SwapOfferError::InvalidQtyRange);
+            }
+        }
+
+        Ok(SwapOffer {
+            from_asset,
+            to_asset,
+            price_num,
+            price_denom,
+            min_qty_from,
+            max_qty_from,
+            offer_expiry,
+        })
+    }
+
+    /// Derive a concrete, single-use [`SwapString`] for a taker requesting
+    /// `qty_from`, generating a fresh [`PaymentPreimage`] and hashing it into
+    /// the swap's [`PaymentHash`], and using `htlc_expiry` as the swap's
+    /// relative HTLC expiry. `now` is the current unix timestamp, checked
+    /// against the offer's absolute `offer_expiry`. The preimage is returned
+    /// alongside the swap string since it's the secret needed to claim the
+    /// resulting HTLC.
+    pub fn derive_swap(
+        &self,
+        qty_from: u64,
+        htlc_expiry: u64,
+        now: u64,
+    ) -> Result<(SwapString, PaymentPreimage), SwapOfferError> {
+        if now >= self.offer_expiry {
+            return Err(SwapOfferError::Expired);
+        }
+        if let Some(min_qty_from) = self.min_qty_from {
+            if qty_from < min_qty_from {
+                return Err(SwapOfferError::QtyTooLow);
+            }
+        }
+        if let Some(max_qty_from) = self.max_qty_from {
+            if qty_from > max_qty_from {
+                return Err(SwapOfferError::QtyTooHigh);
+            }
+        }
+
+        let qty_to = (qty_from as u128)
+            .checked_mul(self.price_num as u128)
+            .map(|product| product / self.price_denom as u128)
+            .and_then(|qty_to| u64::try_from(qty_to).ok())
+            .ok_or(SwapOfferError::InvalidQtyTo)?;
+        if qty_to == 0 {
+            return Err(SwapOfferError::InvalidQtyTo);
+        }
+
+        let swap = Swap {
+            qty_from,
+            qty_to,
+            from_asset: self.from_asset,
+            to_asset: self.to_asset,
+        };
+        let preimage = PaymentPreimage(rand::random());
+        let payment_hash = PaymentHash(sha256::Hash::hash(&preimage.0).to_byte_array());
+
+        Ok((
+            SwapString {
+                swap,
+                expiry: htlc_expiry,
+                payment_hash,
+            },
+            preimage,
+        ))
+    }
+
+    /// Encode this offer into its bech32 representation (HRP `swapoffer`).
+    pub fn to_bech32(&self) -> String {
+        let mut data = Vec::new();
+        push_asset(&mut data, self.from_asset);
+        push_asset(&mut data, self.to_asset);
+        push_int(&mut data, self.price_num);
+        push_int(&mut data, self.price_denom);
+        push_opt_int(&mut data, self.min_qty_from);
+        push_opt_int(&mut data, self.max_qty_from);
+        push_int(&mut data, self.offer_expiry);
+
+        encode_bech32(SWAP_OFFER_HRP, &data)
+    }
+
+    /// Decode a [`SwapOffer`] from its bech32 representation.
+    ///
+    /// Routed through [`SwapOffer::new`] so a checksum-valid offer received
+    /// from a counterparty still has to pass the same validation
+    /// (same-asset check, non-zero price, ordered qty bounds) as one built
+    /// locally; a decoded
+    /// offer is otherwise just as untrusted as any other wire input.
+    pub fn from_bech32(s: &str) -> Result<Self, SwapBech32Error> {
+        let data = decode_bech32_data(s, SWAP_OFFER_HRP)?;
+
+        let mut reader = ByteReader::new(&data);
+        let from_asset = reader.read_asset()?;
+        let to_asset = reader.read_asset()?;
+        let price_num = reader.read_int()?;
+        let price_denom = reader.read_int()?;
+        let min_qty_from = reader.read_opt_int()?;
+        let max_qty_from = reader.read_opt_int()?;
+        let offer_expiry = reader.read_int()?;
+
+        SwapOffer::new(
+            from_asset,
+            to_asset,
+            price_num,
+            price_denom,
+            min_qty_from,
+            max_qty_from,
+            offer_expiry,
+        )
+        .map_err(|_| SwapBech32Error::InvalidOffer)
+    }
+}
+
+#[cfg(test)]
+mod swap_offer_tests {
+    use super::*;
+
+    #[test]
+    fn derive_swap_preimage_hashes_to_payment_hash() {
+        let offer = SwapOffer::new(None, Some(ContractId::from_byte_array([1u8; 32])), 2, 1, None, None, 1_000)
+            .unwrap();
+        let (swap_string, preimage) = offer.derive_swap(10, 144, 0).unwrap();
+        let expected = PaymentHash(sha256::Hash::hash(&preimage.0).to_byte_array());
+        assert_eq!(swap_string.payment_hash, expected);
+        assert_eq!(swap_string.swap.qty_from, 10);
+        assert_eq!(swap_string.swap.qty_to, 20);
+    }
+
+    #[test]
+    fn derive_swap_rejects_expired_offer() {
+        let offer = SwapOffer::new(None, Some(ContractId::from_byte_array([1u8; 32])), 2, 1, None, None, 1_000)
+            .unwrap();
+        assert!(matches!(
+            offer.derive_swap(10, 144, 1_000),
+            Err(SwapOfferError::Expired)
+        ));
+    }
+
+    #[test]
+    fn derive_swap_enforces_qty_bounds() {
+        let offer = SwapOffer::new(
+            None,
+            Some(ContractId::from_byte_array([1u8; 32])),
+            2,
+            1,
+            Some(5),
+            Some(15),
+            1_000,
+        )
+        .unwrap();
+        assert!(matches!(offer.derive_swap(1, 144, 0), Err(SwapOfferError::QtyTooLow)));
+        assert!(matches!(offer.derive_swap(20, 144, 0), Err(SwapOfferError::QtyTooHigh)));
+        assert!(offer.derive_swap(10, 144, 0).is_ok());
+    }
+
+    #[test]
+    fn bech32_round_trips() {
+        let offer = SwapOffer::new(
+            None,
+            Some(ContractId::from_byte_array([4u8; 32])),
+            2,
+            1,
+            Some(5),
+            Some(15),
+            1_000,
+        )
+        .unwrap();
+        let encoded = offer.to_bech32();
+        let decoded = SwapOffer::from_bech32(&encoded).unwrap();
+        assert_eq!(decoded.from_asset, offer.from_asset);
+        assert_eq!(decoded.to_asset, offer.to_asset);
+        assert_eq!(decoded.price_num, offer.price_num);
+        assert_eq!(decoded.price_denom, offer.price_denom);
+        assert_eq!(decoded.min_qty_from, offer.min_qty_from);
+        assert_eq!(decoded.max_qty_from, offer.max_qty_from);
+        assert_eq!(decoded.offer_expiry, offer.offer_expiry);
+    }
+
+    #[test]
+    fn bech32_decode_rejects_a_zero_price_offer() {
+        // Bypasses `SwapOffer::new` by packing the data part directly, the
+        // way a dishonest or buggy counterparty's encoder might.
+        let mut data = Vec::new();
+        push_asset(&mut data, None);
+        push_asset(&mut data, Some(ContractId::from_byte_array([4u8; 32])));
+        push_int(&mut data, 0); // price_num
+        push_int(&mut data, 1); // price_denom
+        push_opt_int(&mut data, None);
+        push_opt_int(&mut data, None);
+        push_int(&mut data, 1_000);
+        let encoded = encode_bech32(SWAP_OFFER_HRP, &data);
+
+        assert!(matches!(
+            SwapOffer::from_bech32(&encoded),
+            Err(SwapBech32Error::InvalidOffer)
+        ));
+    }
+
+    #[test]
+    fn new_rejects_an_inverted_qty_range() {
+        assert!(matches!(
+            SwapOffer::new(None, Some(ContractId::from_byte_array([1u8; 32])), 2, 1, Some(15), Some(5), 1_000),
+            Err(SwapOfferError::InvalidQtyRange)
+        ));
+    }
+}
+
+/// A single UTXO owned by a party to an [`OnchainSwap`], as handed to coin
+/// selection. Callers are expected to already have filtered these down to
+/// UTXOs carrying the right RGB allocation (or plain BTC, for a `None` leg).
+#[derive(Debug, Clone)]
+pub struct OwnedUtxo {
+    pub outpoint: OutPoint,
+    pub value_sat: u64,
+    /// The UTXO's own `script_pubkey`, needed to populate `witness_utxo` on
+    /// the PSBT input so the owner can actually sign it.
+    pub script_pubkey: ScriptBuf,
+}
+
+/// Index of the maker's asset/BTC output within the swap PSBT: always the
+/// first output [`OnchainSwap::build_maker_psbt`] adds.
+const MAKER_ASSET_OUTPUT_INDEX: usize = 0;
+
+/// Validates a raw RGB state transition bundle, as stashed on a swap PSBT
+/// output by [`attach_rgb_transition`], against the node's own RGB contract
+/// stash. Implemented by the node's RGB contract layer; mirrors
+/// [`AssetPrecision`]'s role of deferring a domain lookup this module has
+/// no business performing on its own.
+pub trait RgbTransitionVerifier {
+    /// Returns the contract ID and quantity `transition` commits to
+    /// transferring, or `None` if it's malformed or doesn't validate.
+    fn verify_transition(&self, transition: &[u8]) -> Option<(ContractId, u64)>;
+}
+
+/// An on-chain RGB (or RGB-for-BTC) atomic swap, settled by two parties
+/// cooperatively signing a single PSBT instead of over Lightning HTLCs.
+/// Mirrors [`Swap`]'s asset/quantity fields without the Lightning-specific
+/// `PaymentHash`/relative `expiry`.
+#[derive(Debug, Clone)]
+pub struct OnchainSwap {
+    pub(crate) qty_from: u64,
+    pub(crate) qty_to: u64,
+    pub(crate) from_asset: Option<ContractId>,
+    pub(crate) to_asset: Option<ContractId>,
+}
+
+/// Errors returned while building, joining or finalizing an [`OnchainSwap`]'s
+/// PSBT.
+#[derive(Debug)]
+pub enum OnchainSwapError {
+    /// `from_asset` and `to_asset` are the same asset.
+    SameAsset,
+    /// `qty_from` or `qty_to` is zero.
+    ZeroQty,
+    /// The supplied UTXOs don't cover the required quantity plus fee.
+    InsufficientFunds,
+    /// A counterparty's leg doesn't carry the agreed quantity.
+    QtyMismatch,
+    /// A counterparty's leg doesn't carry the agreed contract ID.
+    AssetMismatch,
+    /// The maker's half of the PSBT is missing its expected output.
+    MissingMakerOutput,
+    /// The taker's half of the PSBT is missing its expected output.
+    MissingTakerOutput,
+    /// An asset output doesn't pay the expected recipient's script.
+    WrongRecipient,
+    /// An RGB-leg asset output's value is not exactly `DUST_LIMIT_SAT`.
+    DustOutput,
+    /// An asset output didn't carry an RGB transition bundle, or
+    /// `rgb_verifier` couldn't validate the one it carried.
+    InvalidRgbTransition,
+    /// Not every input has a finalized signature yet, or (from
+    /// `combine_signed`) the two PSBTs could not be combined.
+    NotFullySigned,
+    /// PSBT extraction into a final transaction failed.
+    ExtractTx,
+}
+
+impl OnchainSwap {
+    pub fn new(
+        qty_from: u64,
+        qty_to: u64,
+        from_asset: Option<ContractId>,
+        to_asset: Option<ContractId>,
+    ) -> Result<Self, OnchainSwapError> {
+        if from_asset == to_asset {
+            return Err(OnchainSwapError::SameAsset);
+        }
+        if qty_from == 0 || qty_to == 0 {
+            return Err(OnchainSwapError::ZeroQty);
+        }
+
+        Ok(OnchainSwap {
+            qty_from,
+            qty_to,
+            from_asset,
+            to_asset,
+        })
+    }
+
+    pub fn from_btc(&self) -> bool {
+        self.from_asset.is_none()
+    }
+
+    pub fn to_btc(&self) -> bool {
+        self.to_asset.is_none()
+    }
+
+    /// Build the maker's half of the swap PSBT: select inputs covering
+    /// `qty_from` (`maker_utxos` is assumed to already be exactly the
+    /// asset-carrying set for an RGB leg), pay `qty_from` to
+    /// `taker_recv_script` plus a dustless BTC change output, and stash the
+    /// maker's RGB state transition bundle on the asset output.
+    pub fn build_maker_psbt(
+        &self,
+        maker_utxos: &[OwnedUtxo],
+        taker_recv_script: ScriptBuf,
+        maker_change_script: ScriptBuf,
+        fee_sat: u64,
+        rgb_transition: Vec<u8>,
+    ) -> Result<PartiallySignedTransaction, OnchainSwapError> {
+        let (inputs, change_sat) = if self.from_btc() {
+            select_coins(maker_utxos, self.qty_from, fee_sat)?
+        } else {
+            select_all_utxos(maker_utxos, fee_sat)?
+        };
+
+        let mut unsigned_tx = Transaction {
+            version: 2,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: inputs
+                .iter()
+                .map(|utxo| TxIn {
+                    previous_output: utxo.outpoint,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: Vec::new(),
+        };
+
+        debug_assert_eq!(unsigned_tx.output.len(), MAKER_ASSET_OUTPUT_INDEX);
+        unsigned_tx.output.push(TxOut {
+            value: if self.from_btc() { self.qty_from } else { DUST_LIMIT_SAT },
+            script_pubkey: taker_recv_script,
+        });
+        if change_sat > DUST_LIMIT_SAT {
+            unsigned_tx.output.push(TxOut {
+                value: change_sat,
+                script_pubkey: maker_change_script,
+            });
+        }
+
+        let mut psbt = PartiallySignedTransaction::from_unsigned_tx(unsigned_tx)
+            .map_err(|_| OnchainSwapError::InsufficientFunds)?;
+        for (input, utxo) in psbt.inputs.iter_mut().zip(&inputs) {
+            input.witness_utxo = Some(TxOut {
+                value: utxo.value_sat,
+                script_pubkey: utxo.script_pubkey.clone(),
+            });
+        }
+        attach_rgb_transition(&mut psbt, MAKER_ASSET_OUTPUT_INDEX, rgb_transition);
+        Ok(psbt)
+    }
+
+    /// Let the taker add their leg to the maker's PSBT, after validating the
+    /// maker's side via [`Self::validate_maker_side`]. Returns the index of
+    /// the taker's new asset output, which the maker must pass to
+    /// [`Self::validate_taker_side`] before signing.
+    pub fn add_taker_side(
+        &self,
+        psbt: &mut PartiallySignedTransaction,
+        maker_recv_script: ScriptBuf,
+        taker_recv_script: &Script,
+        taker_utxos: &[OwnedUtxo],
+        taker_change_script: ScriptBuf,
+        fee_sat: u64,
+        rgb_transition: Vec<u8>,
+        rgb_verifier: &impl RgbTransitionVerifier,
+    ) -> Result<usize, OnchainSwapError> {
+        self.validate_maker_side(psbt, taker_recv_script, rgb_verifier)?;
+
+        let (inputs, change_sat) = if self.to_btc() {
+            select_coins(taker_utxos, self.qty_to, fee_sat)?
+        } else {
+            select_all_utxos(taker_utxos, fee_sat)?
+        };
+
+        for utxo in &inputs {
+            psbt.unsigned_tx.input.push(TxIn {
+                previous_output: utxo.outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            });
+            let mut input = bitcoin::psbt::Input::default();
+            input.witness_utxo = Some(TxOut {
+                value: utxo.value_sat,
+                script_pubkey: utxo.script_pubkey.clone(),
+            });
+            psbt.inputs.push(input);
+        }
+
+        let asset_output_index = psbt.unsigned_tx.output.len();
+        psbt.unsigned_tx.output.push(TxOut {
+            value: if self.to_btc() { self.qty_to } else { DUST_LIMIT_SAT },
+            script_pubkey: maker_recv_script,
+        });
+        psbt.outputs.push(Default::default());
+        if change_sat > DUST_LIMIT_SAT {
+            psbt.unsigned_tx.output.push(TxOut {
+                value: change_sat,
+                script_pubkey: taker_change_script,
+            });
+            psbt.outputs.push(Default::default());
+        }
+
+        attach_rgb_transition(psbt, asset_output_index, rgb_transition);
+        Ok(asset_output_index)
+    }
+
+    /// Validate that the maker's asset output actually pays `taker_recv_script`
+    /// and carries the agreed `from_asset`/`qty_from`, checking an RGB leg's
+    /// contract ID and quantity via `rgb_verifier` rather than trusting a
+    /// header the maker's own code wrote.
+    fn validate_maker_side(
+        &self,
+        psbt: &PartiallySignedTransaction,
+        taker_recv_script: &Script,
+        rgb_verifier: &impl RgbTransitionVerifier,
+    ) -> Result<(), OnchainSwapError> {
+        let tx_output = psbt
+            .unsigned_tx
+            .output
+            .get(MAKER_ASSET_OUTPUT_INDEX)
+            .ok_or(OnchainSwapError::MissingMakerOutput)?;
+
+        if tx_output.script_pubkey != *taker_recv_script {
+            return Err(OnchainSwapError::WrongRecipient);
+        }
+
+        match self.from_asset {
+            None => {
+                if tx_output.value != self.qty_from {
+                    return Err(OnchainSwapError::QtyMismatch);
+                }
+            }
+            Some(expected_contract_id) => {
+                if tx_output.value != DUST_LIMIT_SAT {
+                    return Err(OnchainSwapError::DustOutput);
+                }
+                let psbt_output = psbt
+                    .outputs
+                    .get(MAKER_ASSET_OUTPUT_INDEX)
+                    .ok_or(OnchainSwapError::MissingMakerOutput)?;
+                let transition = read_rgb_transition(psbt_output)?;
+                let (contract_id, qty) = rgb_verifier
+                    .verify_transition(transition)
+                    .ok_or(OnchainSwapError::InvalidRgbTransition)?;
+                if contract_id != expected_contract_id {
+                    return Err(OnchainSwapError::AssetMismatch);
+                }
+                if qty != self.qty_from {
+                    return Err(OnchainSwapError::QtyMismatch);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate that the taker's asset output (at `taker_asset_output_index`,
+    /// as returned by [`Self::add_taker_side`]) actually pays
+    /// `maker_recv_script` and carries the agreed `to_asset`/`qty_to`.
+    /// Mirrors [`Self::validate_maker_side`] for the other side of the
+    /// swap; the maker must call this before signing, or they have no
+    /// guarantee the taker's leg matches the agreed terms.
+    pub fn validate_taker_side(
+        &self,
+        psbt: &PartiallySignedTransaction,
+        maker_recv_script: &Script,
+        taker_asset_output_index: usize,
+        rgb_verifier: &impl RgbTransitionVerifier,
+    ) -> Result<(), OnchainSwapError> {
+        let tx_output = psbt
+            .unsigned_tx
+            .output
+            .get(taker_asset_output_index)
+            .ok_or(OnchainSwapError::MissingTakerOutput)?;
+
+        if tx_output.script_pubkey != *maker_recv_script {
+            return Err(OnchainSwapError::WrongRecipient);
+        }
+
+        match self.to_asset {
+            None => {
+                if tx_output.value != self.qty_to {
+                    return Err(OnchainSwapError::QtyMismatch);
+                }
+            }
+            Some(expected_contract_id) => {
+                if tx_output.value != DUST_LIMIT_SAT {
+                    return Err(OnchainSwapError::DustOutput);
+                }
+                let psbt_output = psbt
+                    .outputs
+                    .get(taker_asset_output_index)
+                    .ok_or(OnchainSwapError::MissingTakerOutput)?;
+                let transition = read_rgb_transition(psbt_output)?;
+                let (contract_id, qty) = rgb_verifier
+                    .verify_transition(transition)
+                    .ok_or(OnchainSwapError::InvalidRgbTransition)?;
+                if contract_id != expected_contract_id {
+                    return Err(OnchainSwapError::AssetMismatch);
+                }
+                if qty != self.qty_to {
+                    return Err(OnchainSwapError::QtyMismatch);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge the maker's and taker's independently-signed PSBTs into one.
+    /// Callers must have already validated each side (the taker via
+    /// `add_taker_side`, the maker via `validate_taker_side`) before
+    /// signing; this does not re-check either leg.
+    pub fn combine_signed(
+        mut maker_psbt: PartiallySignedTransaction,
+        taker_psbt: PartiallySignedTransaction,
+    ) -> Result<PartiallySignedTransaction, OnchainSwapError> {
+        maker_psbt
+            .combine(taker_psbt)
+            .map_err(|_| OnchainSwapError::NotFullySigned)?;
+        Ok(maker_psbt)
+    }
+
+    /// Finalize the cooperatively-signed PSBT into a broadcastable
+    /// transaction, after checking every input actually carries a
+    /// finalized signature.
+    pub fn finalize(&self, psbt: PartiallySignedTransaction) -> Result<Transaction, OnchainSwapError> {
+        let fully_signed = psbt
+            .inputs
+            .iter()
+            .all(|input| input.final_script_witness.is_some() || input.final_script_sig.is_some());
+        if !fully_signed {
+            return Err(OnchainSwapError::NotFullySigned);
+        }
+        psbt.extract_tx().map_err(|_| OnchainSwapError::ExtractTx)
+    }
+}
+
+/// Naive accumulate-until-funded coin selection for a BTC-denominated leg:
+/// pull UTXOs in order until their total covers `target_sat + fee_sat`,
+/// returning the selected UTXOs alongside the dustless leftover change.
+fn select_coins(
+    utxos: &[OwnedUtxo],
+    target_sat: u64,
+    fee_sat: u64,
+) -> Result<(Vec<OwnedUtxo>, u64), OnchainSwapError> {
+    let required = target_sat
+        .checked_add(fee_sat)
+        .ok_or(OnchainSwapError::InsufficientFunds)?;
+
+    let mut selected = Vec::new();
+    let mut total = 0u64;
+    for utxo in utxos {
+        if total >= required {
+            break;
+        }
+        selected.push(utxo.clone());
+        total += utxo.value_sat;
+    }
+
+    if total < required {
+        return Err(OnchainSwapError::InsufficientFunds);
+    }
+
+    Ok((selected, total - required))
+}
+
+/// Coin selection for an RGB-denominated leg: include every UTXO the caller
+/// supplied, since (per [`OwnedUtxo`]'s contract) the caller has already
+/// filtered `utxos` down to exactly the set backing the agreed asset
+/// quantity, and the RGB state transition attached alongside the PSBT
+/// assumes all of them are spent. Dropping any of them here, the way
+/// value-based [`select_coins`] would, would desync the PSBT inputs from
+/// that commitment. The leftover value, after both `fee_sat` and the
+/// [`DUST_LIMIT_SAT`] asset output the caller always adds for this leg, is
+/// treated as dustless change.
+fn select_all_utxos(utxos: &[OwnedUtxo], fee_sat: u64) -> Result<(Vec<OwnedUtxo>, u64), OnchainSwapError> {
+    let total: u64 = utxos.iter().map(|utxo| utxo.value_sat).sum();
+    let change = total
+        .checked_sub(fee_sat)
+        .and_then(|remaining| remaining.checked_sub(DUST_LIMIT_SAT))
+        .ok_or(OnchainSwapError::InsufficientFunds)?;
+    Ok((utxos.to_vec(), change))
+}
+
+/// Stash a serialized RGB state transition bundle on the PSBT output at
+/// `output_index` as a proprietary key/value pair, verbatim, so the other
+/// party can later validate it (see [`RgbTransitionVerifier`]) without
+/// trusting any out-of-band claims about what it contains.
+fn attach_rgb_transition(psbt: &mut PartiallySignedTransaction, output_index: usize, transition: Vec<u8>) {
+    if let Some(output) = psbt.outputs.get_mut(output_index) {
+        output.proprietary.insert(
+            ProprietaryKey {
+                prefix: PSBT_RGB_PREFIX.to_vec(),
+                subtype: 0,
+                key: Vec::new(),
+            },
+            transition,
+        );
+    }
+}
+
+/// Read back the raw RGB state transition bundle stashed by
+/// [`attach_rgb_transition`] from a PSBT output.
+fn read_rgb_transition(output: &bitcoin::psbt::Output) -> Result<&[u8], OnchainSwapError> {
+    output
+        .proprietary
+        .get(&ProprietaryKey {
+            prefix: PSBT_RGB_PREFIX.to_vec(),
+            subtype: 0,
+            key: Vec::new(),
+        })
+        .map(Vec::as_slice)
+        .ok_or(OnchainSwapError::InvalidRgbTransition)
+}
+
+#[cfg(test)]
+mod onchain_swap_tests {
+    use super::*;
+
+    fn utxo(value_sat: u64) -> OwnedUtxo {
+        OwnedUtxo {
+            outpoint: OutPoint::null(),
+            value_sat,
+            script_pubkey: ScriptBuf::new(),
+        }
+    }
+
+    fn script(tag: u8) -> ScriptBuf {
+        ScriptBuf::from(vec![tag])
+    }
+
+    fn total_out(psbt: &PartiallySignedTransaction) -> u64 {
+        psbt.unsigned_tx.output.iter().map(|o| o.value).sum()
+    }
+
+    /// A fake [`RgbTransitionVerifier`] standing in for the node's RGB
+    /// stash: "validates" a transition only if its bytes match exactly
+    /// what the test expects the maker to have attached, returning the
+    /// contract ID/quantity a real implementation would have derived from
+    /// the consignment.
+    struct FakeRgbVerifier {
+        expected_transition: Vec<u8>,
+        contract_id: ContractId,
+        qty: u64,
+    }
+
+    impl RgbTransitionVerifier for FakeRgbVerifier {
+        fn verify_transition(&self, transition: &[u8]) -> Option<(ContractId, u64)> {
+            if transition == self.expected_transition.as_slice() {
+                Some((self.contract_id, self.qty))
+            } else {
+                None
+            }
+        }
+    }
+
+    struct NeverCalledVerifier;
+    impl RgbTransitionVerifier for NeverCalledVerifier {
+        fn verify_transition(&self, _transition: &[u8]) -> Option<(ContractId, u64)> {
+            panic!("RGB verifier should not be consulted for a BTC maker leg");
+        }
+    }
+
+    #[test]
+    fn btc_maker_leg_and_rgb_taker_leg_build_a_balanced_psbt() {
+        let contract_id = ContractId::from_byte_array([9u8; 32]);
+        let swap = OnchainSwap::new(100_000, 50, None, Some(contract_id)).unwrap();
+
+        let maker_utxos = vec![utxo(150_000)];
+        let taker_recv_script = script(1);
+        let maker_change_script = script(2);
+        let maker_fee_sat = 1_000;
+
+        let mut psbt = swap
+            .build_maker_psbt(&maker_utxos, taker_recv_script.clone(), maker_change_script.clone(), maker_fee_sat, vec![])
+            .unwrap();
+        assert_eq!(psbt.unsigned_tx.output[0].value, 100_000);
+        assert_eq!(psbt.unsigned_tx.output[0].script_pubkey, taker_recv_script);
+        assert_eq!(psbt.unsigned_tx.output[1].value, 150_000 - 100_000 - maker_fee_sat);
+
+        let taker_utxos = vec![utxo(1_000), utxo(2_000)];
+        let maker_recv_script = script(3);
+        let taker_change_script = script(4);
+        let taker_fee_sat = 500;
+        let rgb_verifier = NeverCalledVerifier;
+
+        swap.add_taker_side(
+            &mut psbt,
+            maker_recv_script.clone(),
+            &taker_recv_script,
+            &taker_utxos,
+            taker_change_script.clone(),
+            taker_fee_sat,
+            b"taker-transition".to_vec(),
+            &rgb_verifier,
+        )
+        .unwrap();
+
+        assert_eq!(psbt.unsigned_tx.input.len(), 1 + taker_utxos.len());
+        assert_eq!(psbt.unsigned_tx.output[2].value, DUST_LIMIT_SAT);
+        assert_eq!(psbt.unsigned_tx.output[2].script_pubkey, maker_recv_script);
+        assert_eq!(psbt.unsigned_tx.output[3].value, 1_000 + 2_000 - taker_fee_sat - DUST_LIMIT_SAT);
+        assert_eq!(psbt.unsigned_tx.output[3].script_pubkey, taker_change_script);
+
+        let total_in: u64 = 150_000 + 1_000 + 2_000;
+        assert_eq!(total_in, total_out(&psbt) + maker_fee_sat + taker_fee_sat);
+    }
+
+    #[test]
+    fn rgb_maker_leg_and_btc_taker_leg_build_a_balanced_psbt_via_verifier() {
+        let contract_id = ContractId::from_byte_array([5u8; 32]);
+        let swap = OnchainSwap::new(77, 200_000, Some(contract_id), None).unwrap();
+
+        let maker_utxos = vec![utxo(700), utxo(800)];
+        let taker_recv_script = script(1);
+        let maker_change_script = script(2);
+        let maker_fee_sat = 300;
+
+        let mut psbt = swap
+            .build_maker_psbt(
+                &maker_utxos,
+                taker_recv_script.clone(),
+                maker_change_script.clone(),
+                maker_fee_sat,
+                b"maker-transition".to_vec(),
+            )
+            .unwrap();
+        assert_eq!(psbt.unsigned_tx.output[0].value, DUST_LIMIT_SAT);
+        assert_eq!(psbt.unsigned_tx.output[0].script_pubkey, taker_recv_script);
+        assert_eq!(psbt.unsigned_tx.output[1].value, 700 + 800 - maker_fee_sat - DUST_LIMIT_SAT);
+
+        let taker_utxos = vec![utxo(250_000)];
+        let maker_recv_script = script(3);
+        let taker_change_script = script(4);
+        let taker_fee_sat = 1_000;
+        let rgb_verifier = FakeRgbVerifier {
+            expected_transition: b"maker-transition".to_vec(),
+            contract_id,
+            qty: 77,
+        };
+
+        swap.add_taker_side(
+            &mut psbt,
+            maker_recv_script.clone(),
+            &taker_recv_script,
+            &taker_utxos,
+            taker_change_script.clone(),
+            taker_fee_sat,
+            vec![],
+            &rgb_verifier,
+        )
+        .unwrap();
+
+        assert_eq!(psbt.unsigned_tx.output[2].value, 200_000);
+        assert_eq!(psbt.unsigned_tx.output[2].script_pubkey, maker_recv_script);
+        assert_eq!(psbt.unsigned_tx.output[3].value, 250_000 - 200_000 - taker_fee_sat);
+
+        let total_in: u64 = 700 + 800 + 250_000;
+        assert_eq!(total_in, total_out(&psbt) + maker_fee_sat + taker_fee_sat);
+    }
+
+    #[test]
+    fn add_taker_side_rejects_a_maker_output_paying_the_wrong_script() {
+        let contract_id = ContractId::from_byte_array([9u8; 32]);
+        let swap = OnchainSwap::new(100_000, 50, None, Some(contract_id)).unwrap();
+        let maker_utxos = vec![utxo(150_000)];
+        let mut psbt = swap
+            .build_maker_psbt(&maker_utxos, script(1), script(2), 1_000, vec![])
+            .unwrap();
+
+        let attacker_script = script(99);
+        let result = swap.add_taker_side(
+            &mut psbt,
+            script(3),
+            &attacker_script,
+            &[utxo(1_000), utxo(2_000)],
+            script(4),
+            500,
+            b"taker-transition".to_vec(),
+            &NeverCalledVerifier,
+        );
+        assert!(matches!(result, Err(OnchainSwapError::WrongRecipient)));
+    }
+
+    #[test]
+    fn add_taker_side_rejects_a_transition_committing_the_wrong_quantity() {
+        let contract_id = ContractId::from_byte_array([5u8; 32]);
+        let swap = OnchainSwap::new(77, 200_000, Some(contract_id), None).unwrap();
+        let taker_recv_script = script(1);
+        let mut psbt = swap
+            .build_maker_psbt(
+                &[utxo(700), utxo(800)],
+                taker_recv_script.clone(),
+                script(2),
+                300,
+                b"maker-transition".to_vec(),
+            )
+            .unwrap();
+
+        let rgb_verifier = FakeRgbVerifier {
+            expected_transition: b"maker-transition".to_vec(),
+            contract_id,
+            qty: 1, // doesn't match the agreed `qty_from` of 77
+        };
+        let result = swap.add_taker_side(
+            &mut psbt,
+            script(3),
+            &taker_recv_script,
+            &[utxo(250_000)],
+            script(4),
+            1_000,
+            vec![],
+            &rgb_verifier,
+        );
+        assert!(matches!(result, Err(OnchainSwapError::QtyMismatch)));
+    }
+
+    #[test]
+    fn validate_maker_side_rejects_a_sub_dust_rgb_output() {
+        let contract_id = ContractId::from_byte_array([5u8; 32]);
+        let swap = OnchainSwap::new(77, 200_000, Some(contract_id), None).unwrap();
+        let taker_recv_script = script(1);
+        let mut psbt = swap
+            .build_maker_psbt(&[utxo(700), utxo(800)], taker_recv_script.clone(), script(2), 300, b"maker-transition".to_vec())
+            .unwrap();
+        psbt.unsigned_tx.output[MAKER_ASSET_OUTPUT_INDEX].value = 0;
+
+        let rgb_verifier = FakeRgbVerifier {
+            expected_transition: b"maker-transition".to_vec(),
+            contract_id,
+            qty: 77,
+        };
+        let result = swap.validate_maker_side(&psbt, &taker_recv_script, &rgb_verifier);
+        assert!(matches!(result, Err(OnchainSwapError::DustOutput)));
+    }
+
+    #[test]
+    fn maker_validates_taker_side_before_signing() {
+        let contract_id = ContractId::from_byte_array([9u8; 32]);
+        let swap = OnchainSwap::new(100_000, 50, None, Some(contract_id)).unwrap();
+        let taker_recv_script = script(1);
+        let maker_recv_script = script(3);
+        let mut psbt = swap
+            .build_maker_psbt(&[utxo(150_000)], taker_recv_script.clone(), script(2), 1_000, vec![])
+            .unwrap();
+
+        let rgb_verifier = FakeRgbVerifier {
+            expected_transition: b"taker-transition".to_vec(),
+            contract_id,
+            qty: 50,
+        };
+        let asset_output_index = swap
+            .add_taker_side(
+                &mut psbt,
+                maker_recv_script.clone(),
+                &taker_recv_script,
+                &[utxo(1_000), utxo(2_000)],
+                script(4),
+                500,
+                b"taker-transition".to_vec(),
+                &rgb_verifier,
+            )
+            .unwrap();
+
+        assert!(swap
+            .validate_taker_side(&psbt, &maker_recv_script, asset_output_index, &rgb_verifier)
+            .is_ok());
+
+        let attacker_script = script(99);
+        assert!(matches!(
+            swap.validate_taker_side(&psbt, &attacker_script, asset_output_index, &rgb_verifier),
+            Err(OnchainSwapError::WrongRecipient)
+        ));
+    }
+}
+
+/// Role of the local node in a swap registered with a [`SwapRegistry`]:
+/// whether it proposed (maker) or accepted (taker) the swap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SwapRole {
+    Maker,
+    Taker,
+}
+
+/// Lifecycle status of a swap registered with a [`SwapRegistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SwapStatus {
+    Pending,
+    Succeeded,
+    Failed,
+    Expired,
+}
+
+/// A swap accepted into a [`SwapRegistry`], tracking its role and status
+/// alongside the parsed [`SwapString`].
+#[derive(Debug, Clone)]
+pub struct RegisteredSwap {
+    pub swap_string: SwapString,
+    pub role: SwapRole,
+    pub created_at: u64,
+    pub status: SwapStatus,
+}
+
+/// Errors returned by [`SwapRegistry`] operations.
+#[derive(Debug)]
+pub enum SwapRegistryError {
+    Io(std::io::Error),
+    Serde(serde_json::Error),
+    InvalidPaymentHash,
+    InvalidAsset,
+}
+
+impl From<std::io::Error> for SwapRegistryError {
+    fn from(err: std::io::Error) -> Self {
+        SwapRegistryError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SwapRegistryError {
+    fn from(err: serde_json::Error) -> Self {
+        SwapRegistryError::Serde(err)
+    }
+}
+
+/// On-disk record for a [`RegisteredSwap`], using plain serializable fields
+/// (hex strings, Baid58 strings) rather than deriving `Serialize` directly
+/// on the domain types.
+#[derive(Debug, Serialize, Deserialize)]
+struct SwapRecord {
+    payment_hash: String,
+    qty_from: u64,
+    qty_to: u64,
+    from_asset: Option<String>,
+    to_asset: Option<String>,
+    expiry: u64,
+    role: SwapRole,
+    created_at: u64,
+    status: SwapStatus,
+}
+
+impl SwapRecord {
+    fn from_registered(swap: &RegisteredSwap) -> Self {
+        SwapRecord {
+            payment_hash: hex::encode(swap.swap_string.payment_hash.0),
+            qty_from: swap.swap_string.swap.qty_from,
+            qty_to: swap.swap_string.swap.qty_to,
+            from_asset: swap.swap_string.swap.from_asset.map(|id| id.to_string()),
+            to_asset: swap.swap_string.swap.to_asset.map(|id| id.to_string()),
+            expiry: swap.swap_string.expiry,
+            role: swap.role,
+            created_at: swap.created_at,
+            status: swap.status,
+        }
+    }
+
+    fn into_registered(self) -> Result<RegisteredSwap, SwapRegistryError> {
+        let payment_hash = hex_str_to_vec(&self.payment_hash)
+            .and_then(|vec| vec.try_into().ok())
+            .map(PaymentHash)
+            .ok_or(SwapRegistryError::InvalidPaymentHash)?;
+        let from_asset = self
+            .from_asset
+            .map(|s| ContractId::from_str(&s).map_err(|_| SwapRegistryError::InvalidAsset))
+            .transpose()?;
+        let to_asset = self
+            .to_asset
+            .map(|s| ContractId::from_str(&s).map_err(|_| SwapRegistryError::InvalidAsset))
+            .transpose()?;
+
+        let swap_string = SwapString {
+            swap: Swap {
+                qty_from: self.qty_from,
+                qty_to: self.qty_to,
+                from_asset,
+                to_asset,
+            },
+            expiry: self.expiry,
+            payment_hash,
+        };
+
+        Ok(RegisteredSwap {
+            swap_string,
+            role: self.role,
+            created_at: self.created_at,
+            status: self.status,
+        })
+    }
+}
+
+/// Filesystem-backed registry of swaps that have been parsed/accepted but
+/// not yet settled, mirroring the save/list/remove pattern used for
+/// transfer metadata: each swap is persisted as one JSON file named after
+/// its `PaymentHash`, so a restarted node can resume matching HTLCs against
+/// its pending swaps.
+#[derive(Debug, Clone)]
+pub struct SwapRegistry {
+    data_dir: PathBuf,
+}
+
+impl SwapRegistry {
+    pub fn new(data_dir: PathBuf) -> Result<Self, SwapRegistryError> {
+        fs::create_dir_all(&data_dir)?;
+        Self::reap_stale_tmp_files(&data_dir)?;
+        Ok(SwapRegistry { data_dir })
+    }
+
+    fn path_for(&self, payment_hash: &PaymentHash) -> PathBuf {
+        self.data_dir.join(hex::encode(payment_hash.0))
+    }
+
+    /// Remove any `.tmp` file left behind by a `save_swap` interrupted
+    /// between `fs::write` and `fs::rename`. Called once from `new` so a
+    /// restarted node starts from a clean directory rather than relying on
+    /// `list_swaps`'s filename filter alone.
+    fn reap_stale_tmp_files(data_dir: &Path) -> Result<(), SwapRegistryError> {
+        for entry in fs::read_dir(data_dir)? {
+            let entry = entry?;
+            if entry.path().extension().is_some_and(|ext| ext == "tmp") {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+        Ok(())
+    }
+
+    /// Persist `swap`, writing to a temporary file and renaming it into
+    /// place so a crash mid-write can never leave a partially-written,
+    /// corrupt record behind.
+    pub fn save_swap(&self, swap: &RegisteredSwap) -> Result<(), SwapRegistryError> {
+        let record = SwapRecord::from_registered(swap);
+        let path = self.path_for(&swap.swap_string.payment_hash);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(&record)?)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// List registered swaps, optionally filtered by `status` and/or an
+    /// asset appearing as either the `from_asset` or `to_asset` leg.
+    ///
+    /// A record that can't be read, parsed or reconstructed (e.g. a
+    /// corrupted or hand-edited file) is logged and skipped rather than
+    /// failing the whole listing.
+    pub fn list_swaps(
+        &self,
+        status: Option<SwapStatus>,
+        asset: Option<ContractId>,
+    ) -> Result<Vec<RegisteredSwap>, SwapRegistryError> {
+        let mut swaps = Vec::new();
+        for entry in fs::read_dir(&self.data_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            if !is_payment_hash_file_name(&entry.file_name()) {
+                // Leftover `.tmp` file from a `save_swap` interrupted between
+                // `fs::write` and `fs::rename`, or some other stray file: its
+                // name doesn't match a payment hash, so it was never a
+                // completed record and must not surface as one.
+                continue;
+            }
+
+            let bytes = match fs::read(entry.path()) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    log::warn!("swap registry: skipping unreadable record {:?}: {err}", entry.path());
+                    continue;
+                }
+            };
+            let record: SwapRecord = match serde_json::from_slice(&bytes) {
+                Ok(record) => record,
+                Err(err) => {
+                    log::warn!("swap registry: skipping malformed record {:?}: {err}", entry.path());
+                    continue;
+                }
+            };
+            let swap = match record.into_registered() {
+                Ok(swap) => swap,
+                Err(err) => {
+                    log::warn!("swap registry: skipping invalid record {:?}: {err:?}", entry.path());
+                    continue;
+                }
+            };
+
+            if status.is_some_and(|status| swap.status != status) {
+                continue;
+            }
+            if asset.is_some_and(|asset| {
+                swap.swap_string.swap.from_asset != Some(asset)
+                    && swap.swap_string.swap.to_asset != Some(asset)
+            }) {
+                continue;
+            }
+
+            swaps.push(swap);
+        }
+        Ok(swaps)
+    }
+
+    pub fn remove_swap(&self, payment_hash: &PaymentHash) -> Result<(), SwapRegistryError> {
+        match fs::remove_file(self.path_for(payment_hash)) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Mark every `Pending` swap whose relative HTLC `expiry` has elapsed
+    /// (relative to `created_at`) as `Expired`, so stale entries don't
+    /// accumulate. Returns the number of swaps pruned. Intended to be
+    /// called periodically by a background task.
+    pub fn prune_expired(&self, now: u64) -> Result<usize, SwapRegistryError> {
+        let mut pruned = 0;
+        for mut swap in self.list_swaps(Some(SwapStatus::Pending), None)? {
+            if now.saturating_sub(swap.created_at) >= swap.swap_string.expiry {
+                swap.status = SwapStatus::Expired;
+                self.save_swap(&swap)?;
+                pruned += 1;
+            }
+        }
+        Ok(pruned)
+    }
+}
+
+/// Whether `file_name` looks like a `path_for`-produced record name, i.e.
+/// the lowercase hex encoding of a 32-byte [`PaymentHash`]. Anything else
+/// (a `.tmp` file left behind by an interrupted `save_swap`, a stray file a
+/// user dropped into the data dir, ...) is not a completed record and must
+/// be skipped by `list_swaps` rather than parsed as one.
+fn is_payment_hash_file_name(file_name: &OsStr) -> bool {
+    match file_name.to_str() {
+        Some(name) => name.len() == 64 && name.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod swap_registry_tests {
+    use super::*;
+
+    fn test_registry(name: &str) -> SwapRegistry {
+        let data_dir = std::env::temp_dir().join(format!("rgb_lightning_node_swap_registry_test_{name}"));
+        let _ = fs::remove_dir_all(&data_dir);
+        SwapRegistry::new(data_dir).unwrap()
+    }
+
+    fn sample(payment_hash: [u8; 32], created_at: u64, expiry: u64) -> RegisteredSwap {
+        RegisteredSwap {
+            swap_string: SwapString {
+                swap: Swap {
+                    qty_from: 10,
+                    qty_to: 20,
+                    from_asset: None,
+                    to_asset: Some(ContractId::from_byte_array([2u8; 32])),
+                },
+                expiry,
+                payment_hash: PaymentHash(payment_hash),
+            },
+            role: SwapRole::Maker,
+            created_at,
+            status: SwapStatus::Pending,
+        }
+    }
+
+    #[test]
+    fn save_list_and_remove_round_trip() {
+        let registry = test_registry("round_trip");
+        let swap = sample([1u8; 32], 0, 100);
+        registry.save_swap(&swap).unwrap();
+
+        let listed = registry.list_swaps(None, None).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].swap_string.payment_hash, swap.swap_string.payment_hash);
+
+        registry.remove_swap(&swap.swap_string.payment_hash).unwrap();
+        assert!(registry.list_swaps(None, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn list_swaps_filters_by_status_and_asset() {
+        let registry = test_registry("filters");
+        let mut pending = sample([2u8; 32], 0, 100);
+        registry.save_swap(&pending).unwrap();
+        pending.status = SwapStatus::Succeeded;
+        pending.swap_string.payment_hash = PaymentHash([3u8; 32]);
+        registry.save_swap(&pending).unwrap();
+
+        let pending_only = registry.list_swaps(Some(SwapStatus::Pending), None).unwrap();
+        assert_eq!(pending_only.len(), 1);
+
+        let by_asset = registry
+            .list_swaps(None, Some(ContractId::from_byte_array([2u8; 32])))
+            .unwrap();
+        assert_eq!(by_asset.len(), 2);
+
+        let by_other_asset = registry
+            .list_swaps(None, Some(ContractId::from_byte_array([9u8; 32])))
+            .unwrap();
+        assert!(by_other_asset.is_empty());
+    }
+
+    #[test]
+    fn prune_expired_marks_stale_pending_swaps() {
+        let registry = test_registry("prune");
+        registry.save_swap(&sample([4u8; 32], 0, 100)).unwrap();
+        registry.save_swap(&sample([5u8; 32], 0, 1_000)).unwrap();
+
+        let pruned = registry.prune_expired(500).unwrap();
+        assert_eq!(pruned, 1);
+
+        let expired = registry.list_swaps(Some(SwapStatus::Expired), None).unwrap();
+        assert_eq!(expired.len(), 1);
+        let still_pending = registry.list_swaps(Some(SwapStatus::Pending), None).unwrap();
+        assert_eq!(still_pending.len(), 1);
+    }
+
+    #[test]
+    fn list_swaps_skips_malformed_records_instead_of_failing() {
+        let registry = test_registry("malformed");
+        registry.save_swap(&sample([6u8; 32], 0, 100)).unwrap();
+        fs::write(registry.path_for(&PaymentHash([7u8; 32])), b"not json").unwrap();
+
+        let listed = registry.list_swaps(None, None).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].swap_string.payment_hash, PaymentHash([6u8; 32]));
+    }
+
+    #[test]
+    fn list_swaps_ignores_stale_tmp_files() {
+        let registry = test_registry("stale_tmp");
+        registry.save_swap(&sample([8u8; 32], 0, 100)).unwrap();
+        let leftover = registry.path_for(&PaymentHash([9u8; 32])).with_extension("tmp");
+        fs::write(&leftover, serde_json::to_vec(&SwapRecord::from_registered(&sample([9u8; 32], 0, 100))).unwrap())
+            .unwrap();
+
+        let listed = registry.list_swaps(None, None).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].swap_string.payment_hash, PaymentHash([8u8; 32]));
+    }
+
+    #[test]
+    fn new_reaps_stale_tmp_files_left_by_an_interrupted_save() {
+        let data_dir = std::env::temp_dir().join("rgb_lightning_node_swap_registry_test_reap");
+        let _ = fs::remove_dir_all(&data_dir);
+        fs::create_dir_all(&data_dir).unwrap();
+        let leftover = data_dir.join(hex::encode([9u8; 32])).with_extension("tmp");
+        fs::write(&leftover, b"stale").unwrap();
+
+        SwapRegistry::new(data_dir).unwrap();
+
+        assert!(!leftover.exists());
+    }
+}
+
+/// Decimal precision of BTC (i.e. satoshis per BTC), used for the `btc` leg
+/// of a swap when converting between base units and human-readable
+/// quantities.
+pub const BTC_PRECISION: u8 = 8;
+
+/// Source of an asset's declared decimal precision, needed to convert a
+/// human-readable quantity (e.g. `"1.5"`) into integer base units and back.
+/// Implemented by the node's asset registry; the `btc` leg always uses
+/// [`BTC_PRECISION`] and never consults this.
+pub trait AssetPrecision {
+    fn precision(&self, contract_id: ContractId) -> Option<u8>;
+}
+
+/// Errors returned while parsing or formatting a human-readable swap
+/// quantity.
+#[derive(Debug)]
+pub enum QtyParseError {
+    /// The quantity string wasn't a valid decimal number (with an optional
+    /// SI-style suffix).
+    InvalidFormat,
+    /// The asset's decimal precision could not be resolved.
+    UnknownAsset,
+    /// The quantity has more fractional digits than the asset's precision
+    /// can represent without loss.
+    PrecisionLoss,
+    /// Converting the quantity to base units overflowed `u64`.
+    Overflow,
+}
+
+/// Parse a human-readable quantity such as `"1.5"`, `"2"` or `"2.5k"` into
+/// integer base units, resolved against `precision` decimal places.
+///
+/// An optional single-letter SI-style suffix (`k`=10^3, `M`=10^6, `G`=10^9,
+/// `T`=10^12) scales the quantity before it's converted to base units. The
+/// conversion is done on the digits directly (no floating point) and fails
+/// with [`QtyParseError::PrecisionLoss`] rather than rounding if the
+/// quantity has more fractional digits than `precision` (plus any SI scale)
+/// can represent, and with [`QtyParseError::Overflow`] rather than wrapping
+/// if the result doesn't fit in a `u64`.
+pub fn parse_decimal_qty(qty: &str, precision: u8) -> Result<u64, QtyParseError> {
+    let (qty, si_exp) = match qty.chars().last() {
+        Some(c) if c.is_ascii_alphabetic() => (
+            &qty[..qty.len() - c.len_utf8()],
+            si_exponent(c).ok_or(QtyParseError::InvalidFormat)?,
+        ),
+        _ => (qty, 0i32),
+    };
+
+    if qty.is_empty() || !qty.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return Err(QtyParseError::InvalidFormat);
+    }
+
+    let (int_part, frac_part) = match qty.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, frac_part),
+        None => (qty, ""),
+    };
+    if int_part.is_empty() || frac_part.contains('.') {
+        return Err(QtyParseError::InvalidFormat);
+    }
+    // Trailing fractional zeros don't change the value.
+    let frac_part = frac_part.trim_end_matches('0');
+
+    // Every character is an ASCII digit by now, so parsing can only fail
+    // because the digit string is too large for a `u128`.
+    let digits: u128 = format!("{int_part}{frac_part}")
+        .parse()
+        .map_err(|_| QtyParseError::Overflow)?;
+    let scale = i32::from(precision) + si_exp - frac_part.len() as i32;
+
+    let base_units = if scale >= 0 {
+        let multiplier = 10u128.checked_pow(scale as u32).ok_or(QtyParseError::Overflow)?;
+        digits.checked_mul(multiplier).ok_or(QtyParseError::Overflow)?
+    } else {
+        let divisor = 10u128.checked_pow((-scale) as u32).ok_or(QtyParseError::Overflow)?;
+        if digits % divisor != 0 {
+            return Err(QtyParseError::PrecisionLoss);
+        }
+        digits / divisor
+    };
+
+    u64::try_from(base_units).map_err(|_| QtyParseError::Overflow)
+}
+
+/// Format base units back into a human-readable decimal quantity (the
+/// inverse of [`parse_decimal_qty`], without an SI suffix), trimming
+/// trailing fractional zeros and the decimal point itself when the
+/// quantity is a whole number.
+///
+/// Fails with [`QtyParseError::Overflow`] rather than panicking or
+/// wrapping if `precision` (an asset-declared value, not bounded anywhere
+/// upstream) is too large for `10u64.pow(precision)` to represent.
+pub fn format_decimal_qty(base_units: u64, precision: u8) -> Result<String, QtyParseError> {
+    let divisor = 10u64.checked_pow(u32::from(precision)).ok_or(QtyParseError::Overflow)?;
+    let int_part = base_units / divisor;
+    let frac_part = base_units % divisor;
+    if frac_part == 0 {
+        return Ok(int_part.to_string());
+    }
+    let frac_str = format!("{:0width$}", frac_part, width = precision as usize);
+    Ok(format!("{int_part}.{}", frac_str.trim_end_matches('0')))
+}
+
+fn si_exponent(suffix: char) -> Option<i32> {
+    match suffix {
+        'k' | 'K' => Some(3),
+        'M' => Some(6),
+        'G' => Some(9),
+        'T' => Some(12),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod decimal_qty_tests {
+    use super::*;
+
+    #[test]
+    fn parses_plain_integers() {
+        assert_eq!(parse_decimal_qty("5", 2).unwrap(), 500);
+        assert_eq!(parse_decimal_qty("0", 2).unwrap(), 0);
+    }
+
+    #[test]
+    fn parses_decimal_points() {
+        assert_eq!(parse_decimal_qty("1.5", 2).unwrap(), 150);
+        assert_eq!(parse_decimal_qty("1.23", 2).unwrap(), 123);
+    }
+
+    #[test]
+    fn rejects_a_missing_integer_part() {
+        assert!(matches!(parse_decimal_qty(".5", 2), Err(QtyParseError::InvalidFormat)));
+    }
+
+    #[test]
+    fn parses_si_suffixes() {
+        assert_eq!(parse_decimal_qty("2k", 0).unwrap(), 2_000);
+        assert_eq!(parse_decimal_qty("2.5k", 2).unwrap(), 250_000);
+        assert_eq!(parse_decimal_qty("1M", 0).unwrap(), 1_000_000);
+    }
+
+    #[test]
+    fn rejects_precision_loss() {
+        // 3 fractional digits against only 2 decimals of precision.
+        assert!(matches!(
+            parse_decimal_qty("1.235", 2),
+            Err(QtyParseError::PrecisionLoss)
+        ));
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert!(matches!(
+            parse_decimal_qty("99999999999999999999", 0),
+            Err(QtyParseError::Overflow)
+        ));
+    }
+
+    #[test]
+    fn rejects_overflow_from_an_excessively_long_fractional_part() {
+        // 50 fractional digits against precision 8 drives `scale` to -42,
+        // which must not be handed to `pow` unchecked.
+        let qty = format!("0.{}1", "0".repeat(49));
+        assert!(matches!(parse_decimal_qty(&qty, 8), Err(QtyParseError::Overflow)));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(matches!(parse_decimal_qty("", 2), Err(QtyParseError::InvalidFormat)));
+        assert!(matches!(parse_decimal_qty("1.2.3", 2), Err(QtyParseError::InvalidFormat)));
+        assert!(matches!(parse_decimal_qty("abc", 2), Err(QtyParseError::InvalidFormat)));
+    }
+
+    #[test]
+    fn format_and_parse_round_trip() {
+        for (qty, precision) in [(150u64, 2u8), (100, 2), (5, 0), (123_456, 6)] {
+            let formatted = format_decimal_qty(qty, precision).unwrap();
+            assert_eq!(parse_decimal_qty(&formatted, precision).unwrap(), qty);
+        }
+    }
+
+    #[test]
+    fn format_trims_trailing_zeros() {
+        assert_eq!(format_decimal_qty(150, 2).unwrap(), "1.5");
+        assert_eq!(format_decimal_qty(100, 2).unwrap(), "1");
+        assert_eq!(format_decimal_qty(0, 2).unwrap(), "0");
+    }
+
+    #[test]
+    fn format_rejects_precision_overflow() {
+        assert!(matches!(format_decimal_qty(12345, 64), Err(QtyParseError::Overflow)));
+        assert!(matches!(format_decimal_qty(12345, 20), Err(QtyParseError::Overflow)));
+    }
+}
+
+/// Resolve the decimal precision for one leg of a swap: [`BTC_PRECISION`]
+/// for the `btc` leg, or the asset's declared precision otherwise.
+fn resolve_precision(
+    asset: Option<ContractId>,
+    precision_source: &impl AssetPrecision,
+) -> Result<u8, QtyParseError> {
+    match asset {
+        None => Ok(BTC_PRECISION),
+        Some(contract_id) => precision_source
+            .precision(contract_id)
+            .ok_or(QtyParseError::UnknownAsset),
+    }
+}
+
+/// Errors returned by [`SwapString::from_str_with_precision`].
+#[derive(Debug)]
+pub enum SwapParseError {
+    WrongNumberOfParts,
+    InvalidAsset,
+    InvalidExpiry,
+    InvalidPaymentHash,
+    InvalidQty(QtyParseError),
+    ZeroQtyOrExpiry,
+    SameAsset,
+}
+
+impl From<QtyParseError> for SwapParseError {
+    fn from(err: QtyParseError) -> Self {
+        SwapParseError::InvalidQty(err)
+    }
+}
+
+impl SwapString {
+    /// Parse a swap string in the same slash-separated format as
+    /// [`FromStr`], except `qty_from`/`qty_to` are given as human-readable
+    /// decimal quantities (e.g. `"1.5"`, `"2.5k"`) instead of raw base
+    /// units, converted via [`parse_decimal_qty`] against each asset's
+    /// precision as resolved by `precision_source`.
+    pub fn from_str_with_precision(
+        s: &str,
+        precision_source: &impl AssetPrecision,
+    ) -> Result<Self, SwapParseError> {
+        let mut iter = s.split('/');
+        let qty_from = iter.next();
+        let from_asset = iter.next();
+        let qty_to = iter.next();
+        let to_asset = iter.next();
+        let expiry = iter.next();
+        let payment_hash = iter.next();
+
+        if payment_hash.is_none() || iter.next().is_some() {
+            return Err(SwapParseError::WrongNumberOfParts);
+        }
+
+        let from_asset =
+            parse_opt_asset(from_asset.unwrap()).map_err(|_| SwapParseError::InvalidAsset)?;
+        let to_asset =
+            parse_opt_asset(to_asset.unwrap()).map_err(|_| SwapParseError::InvalidAsset)?;
+
+        let qty_from = parse_decimal_qty(
+            qty_from.unwrap(),
+            resolve_precision(from_asset, precision_source)?,
+        )?;
+        let qty_to = parse_decimal_qty(
+            qty_to.unwrap(),
+            resolve_precision(to_asset, precision_source)?,
+        )?;
+
+        let expiry = expiry
+            .unwrap()
+            .parse::<u64>()
+            .map_err(|_| SwapParseError::InvalidExpiry)?;
+        let payment_hash = hex_str_to_vec(payment_hash.unwrap())
+            .and_then(|vec| vec.try_into().ok())
+            .map(PaymentHash)
+            .ok_or(SwapParseError::InvalidPaymentHash)?;
+
+        if qty_from == 0 || qty_to == 0 || expiry == 0 {
+            return Err(SwapParseError::ZeroQtyOrExpiry);
+        }
+
+        let swap = Swap {
+            qty_from,
+            qty_to,
+            from_asset,
+            to_asset,
+        };
+        if swap.same_asset() {
+            return Err(SwapParseError::SameAsset);
+        }
+
+        Ok(SwapString {
+            swap,
+            expiry,
+            payment_hash,
+        })
+    }
+}
+
+impl Swap {
+    /// Render `qty_from` back into a human-readable decimal quantity, per
+    /// [`format_decimal_qty`].
+    pub fn format_qty_from(&self, precision_source: &impl AssetPrecision) -> Result<String, QtyParseError> {
+        let precision = resolve_precision(self.from_asset, precision_source)?;
+        format_decimal_qty(self.qty_from, precision)
+    }
+
+    /// Render `qty_to` back into a human-readable decimal quantity, per
+    /// [`format_decimal_qty`].
+    pub fn format_qty_to(&self, precision_source: &impl AssetPrecision) -> Result<String, QtyParseError> {
+        let precision = resolve_precision(self.to_asset, precision_source)?;
+        format_decimal_qty(self.qty_to, precision)
+    }
+}